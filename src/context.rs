@@ -0,0 +1,132 @@
+// Copyright 2020-2021 the Deno authors. All rights reserved. MIT license.
+use crate::fixer::{apply_fixes, LintFix};
+use std::cell::RefCell;
+use swc_common::{BytePos, Span};
+
+/// A single lint violation recorded against a particular rule, optionally
+/// carrying a suggested [`LintFix`] that resolves it.
+#[derive(Debug, Clone)]
+pub struct LintDiagnostic {
+  pub span: Span,
+  pub code: String,
+  pub message: String,
+  pub hint: Option<String>,
+  pub fix: Option<LintFix>,
+}
+
+/// Per-file state threaded through every rule's `lint_program`. Rules never
+/// construct [`LintDiagnostic`]s directly; they go through the
+/// `add_diagnostic*` methods below instead.
+pub struct Context<'view> {
+  diagnostics: RefCell<Vec<LintDiagnostic>>,
+  file_text: &'view str,
+}
+
+impl<'view> Context<'view> {
+  pub fn new(file_text: &'view str) -> Self {
+    Self {
+      diagnostics: RefCell::new(Vec::new()),
+      file_text,
+    }
+  }
+
+  /// The full source text of the file currently being linted. Rules that
+  /// build autofixes use this to inspect the exact source around a span,
+  /// since whitespace and formatting can't be assumed from the AST alone.
+  pub fn file_text(&self) -> &'view str {
+    self.file_text
+  }
+
+  pub fn diagnostics(&self) -> Vec<LintDiagnostic> {
+    self.diagnostics.borrow().clone()
+  }
+
+  /// The library-facing entry point for autofixing: applies every
+  /// [`LintFix`] recorded on this context's diagnostics (via
+  /// `add_diagnostic_with_fix`) to `file_text` and returns the corrected
+  /// source. `file_start` is the `BytePos` the file began at in the
+  /// `SourceMap` its spans were produced from (see `apply_fixes`).
+  pub fn apply_fixes(&self, file_start: BytePos) -> String {
+    let fixes: Vec<LintFix> = self
+      .diagnostics
+      .borrow()
+      .iter()
+      .filter_map(|d| d.fix.clone())
+      .collect();
+    apply_fixes(self.file_text, file_start, &fixes)
+  }
+
+  pub fn add_diagnostic(&mut self, span: Span, code: &str, message: &str) {
+    self.diagnostics.borrow_mut().push(LintDiagnostic {
+      span,
+      code: code.to_string(),
+      message: message.to_string(),
+      hint: None,
+      fix: None,
+    });
+  }
+
+  pub fn add_diagnostic_with_hint(
+    &mut self,
+    span: Span,
+    code: &str,
+    message: &str,
+    hint: &str,
+  ) {
+    self.diagnostics.borrow_mut().push(LintDiagnostic {
+      span,
+      code: code.to_string(),
+      message: message.to_string(),
+      hint: Some(hint.to_string()),
+      fix: None,
+    });
+  }
+
+  /// Like [`Context::add_diagnostic_with_hint`], but additionally attaches a
+  /// [`LintFix`] that, when applied, resolves the violation.
+  pub fn add_diagnostic_with_fix(
+    &mut self,
+    span: Span,
+    code: &str,
+    message: &str,
+    hint: &str,
+    fix: LintFix,
+  ) {
+    self.diagnostics.borrow_mut().push(LintDiagnostic {
+      span,
+      code: code.to_string(),
+      message: message.to_string(),
+      hint: Some(hint.to_string()),
+      fix: Some(fix),
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::fixer::LintFixEdit;
+  use swc_common::SyntaxContext;
+
+  #[test]
+  fn apply_fixes_applies_every_recorded_fix() {
+    let source = "async function f() {}";
+    let mut context = Context::new(source);
+    let span = Span::new(BytePos(1), BytePos(7), SyntaxContext::empty());
+    let fix = LintFix {
+      description: "Remove `async` keyword".to_string(),
+      edits: vec![LintFixEdit::new(span, "")],
+    };
+    context.add_diagnostic_with_fix(span, "some-rule", "message", "hint", fix);
+    assert_eq!(context.apply_fixes(BytePos(1)), "function f() {}");
+  }
+
+  #[test]
+  fn apply_fixes_is_a_no_op_when_no_diagnostic_has_a_fix() {
+    let source = "function f() {}";
+    let mut context = Context::new(source);
+    let span = Span::new(BytePos(1), BytePos(9), SyntaxContext::empty());
+    context.add_diagnostic_with_hint(span, "some-rule", "message", "hint");
+    assert_eq!(context.apply_fixes(BytePos(1)), source);
+  }
+}
@@ -0,0 +1,119 @@
+// Copyright 2020-2021 the Deno authors. All rights reserved. MIT license.
+use swc_common::BytePos;
+use swc_common::Span;
+
+/// A single textual edit: replace the bytes covered by `span` with
+/// `replacement_text`. `span` is expected to use the same coordinate space
+/// as the `SourceMap` the file was parsed with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintFixEdit {
+  pub span: Span,
+  pub replacement_text: String,
+}
+
+impl LintFixEdit {
+  pub fn new(span: Span, replacement_text: impl Into<String>) -> Self {
+    Self {
+      span,
+      replacement_text: replacement_text.into(),
+    }
+  }
+}
+
+/// A suggested fix for a lint diagnostic, made up of one or more
+/// [`LintFixEdit`]s that, applied together, resolve the violation. Rules
+/// register these via `Context::add_diagnostic_with_fix` instead of
+/// constructing them directly.
+#[derive(Debug, Clone)]
+pub struct LintFix {
+  /// A short, human-readable description of what the fix does, e.g.
+  /// "Remove `async` keyword".
+  pub description: String,
+  pub edits: Vec<LintFixEdit>,
+}
+
+/// Applies `fixes` to `source`, returning the corrected source text.
+///
+/// `file_start` is the `BytePos` that the enclosing `SourceFile` begins at
+/// within the `SourceMap` the spans were produced from; it's subtracted
+/// from each span to get an offset into `source` itself.
+///
+/// Edits are applied in descending order of their span's start position so
+/// that applying one edit never invalidates the byte offsets of edits that
+/// haven't been applied yet. Edits aren't expected to overlap; if two do,
+/// the one with the later start position wins and the earlier one (which
+/// would now apply to stale offsets) is skipped.
+pub fn apply_fixes<'a>(
+  source: &str,
+  file_start: BytePos,
+  fixes: impl IntoIterator<Item = &'a LintFix>,
+) -> String {
+  let mut edits: Vec<&LintFixEdit> =
+    fixes.into_iter().flat_map(|fix| fix.edits.iter()).collect();
+  edits.sort_by(|a, b| b.span.lo().cmp(&a.span.lo()));
+
+  let mut result = source.to_string();
+  let mut min_applied_lo = u32::MAX;
+  for edit in edits {
+    let lo = (edit.span.lo() - file_start).0;
+    let hi = (edit.span.hi() - file_start).0;
+    if hi > min_applied_lo {
+      continue;
+    }
+    result.replace_range(lo as usize..hi as usize, &edit.replacement_text);
+    min_applied_lo = lo;
+  }
+  result
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use swc_common::SyntaxContext;
+
+  fn span(lo: u32, hi: u32) -> Span {
+    Span::new(BytePos(lo), BytePos(hi), SyntaxContext::empty())
+  }
+
+  fn fix(edits: Vec<LintFixEdit>) -> LintFix {
+    LintFix {
+      description: "test fix".to_string(),
+      edits,
+    }
+  }
+
+  #[test]
+  fn applies_single_edit() {
+    let source = "async function f() {}";
+    let fixes = vec![fix(vec![LintFixEdit::new(span(1, 7), "")])];
+    assert_eq!(
+      apply_fixes(source, BytePos(1), &fixes),
+      "function f() {}"
+    );
+  }
+
+  #[test]
+  fn applies_multiple_non_overlapping_edits_in_descending_order() {
+    let source = "async function f() { async function g() {} }";
+    let fixes = vec![fix(vec![
+      LintFixEdit::new(span(1, 7), ""),
+      LintFixEdit::new(span(22, 28), ""),
+    ])];
+    assert_eq!(
+      apply_fixes(source, BytePos(1), &fixes),
+      "function f() { function g() {} }"
+    );
+  }
+
+  #[test]
+  fn skips_overlapping_edit() {
+    let source = "abcdef";
+    let fixes = vec![fix(vec![
+      LintFixEdit::new(span(1, 4), "X"),
+      LintFixEdit::new(span(3, 6), "Y"),
+    ])];
+    // The edit starting later (span 3..6) is applied first; the earlier one
+    // overlaps it and is skipped rather than corrupting the output.
+    assert_eq!(apply_fixes(source, BytePos(1), &fixes), "abYf");
+  }
+}
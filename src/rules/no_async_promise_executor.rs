@@ -1,12 +1,75 @@
 // Copyright 2020-2021 the Deno authors. All rights reserved. MIT license.
 use super::{Context, LintRule, ProgramRef, DUMMY_NODE};
-use swc_ecmascript::ast::{Expr, NewExpr, ParenExpr};
+use crate::fixer::{LintFix, LintFixEdit};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use swc_atoms::JsWord;
+use swc_common::{BytePos, Span};
+use swc_ecmascript::ast::{
+  BlockStmt, ClassDecl, Expr, FnDecl, NewExpr, ParenExpr, Pat, VarDecl,
+  VarDeclKind,
+};
 use swc_ecmascript::visit::noop_visit_type;
 use swc_ecmascript::visit::Node;
+use swc_ecmascript::visit::Visit;
 use swc_ecmascript::visit::VisitAll;
 use swc_ecmascript::visit::VisitAllWith;
+use swc_ecmascript::visit::VisitWith;
 
-pub struct NoAsyncPromiseExecutor;
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct NoAsyncPromiseExecutorOptions {
+  /// Additional constructor names to treat as `Promise`-like (e.g. a
+  /// subclass or a third-party promise library), on top of `Promise`
+  /// itself.
+  pub additional_constructors: Vec<String>,
+}
+
+impl Default for NoAsyncPromiseExecutorOptions {
+  fn default() -> Self {
+    Self {
+      additional_constructors: Vec::new(),
+    }
+  }
+}
+
+pub struct NoAsyncPromiseExecutor {
+  promise_like_names: HashSet<JsWord>,
+}
+
+impl Default for NoAsyncPromiseExecutor {
+  fn default() -> Self {
+    *Self::new_with_options(NoAsyncPromiseExecutorOptions::default())
+  }
+}
+
+impl NoAsyncPromiseExecutor {
+  pub fn new_with_options(
+    options: NoAsyncPromiseExecutorOptions,
+  ) -> Box<Self> {
+    let mut rule = Box::new(Self {
+      promise_like_names: HashSet::new(),
+    });
+    rule.configure(options);
+    rule
+  }
+
+  /// Applies `options` to an already-constructed rule, replacing its set of
+  /// `Promise`-like constructor names (`Promise` itself is always kept,
+  /// regardless of what `options` contains). `LintRule::new()` has no way to
+  /// accept per-rule config, since it's called generically for every rule;
+  /// a config-loading step is expected to call this afterwards for rules
+  /// that were configured in the user's lint config.
+  pub fn configure(&mut self, options: NoAsyncPromiseExecutorOptions) {
+    self.promise_like_names = options
+      .additional_constructors
+      .into_iter()
+      .map(JsWord::from)
+      .collect();
+    self.promise_like_names.insert(JsWord::from("Promise"));
+  }
+}
 
 const CODE: &str = "no-async-promise-executor";
 const MESSAGE: &str = "Async promise executors are not allowed";
@@ -14,8 +77,15 @@ const HINT: &str =
   "Remove `async` from executor function and adjust promise code as needed";
 
 impl LintRule for NoAsyncPromiseExecutor {
+  // `LintRule::new()` takes no arguments, so it can only ever build the
+  // unconfigured, `Promise`-only default here. Applying a user's configured
+  // `additionalConstructors` requires the rule-registry/config-loading step
+  // to call `configure`/`new_with_options` with the parsed rule config after
+  // construction; that step doesn't exist in this tree, so configuring this
+  // rule's `additionalConstructors` through the CLI's lint config has no
+  // effect yet. See `docs()` below.
   fn new() -> Box<Self> {
-    Box::new(NoAsyncPromiseExecutor)
+    Box::new(NoAsyncPromiseExecutor::default())
   }
 
   fn tags(&self) -> &'static [&'static str] {
@@ -31,7 +101,15 @@ impl LintRule for NoAsyncPromiseExecutor {
     context: &mut Context<'view>,
     program: ProgramRef<'view>,
   ) {
-    let mut visitor = NoAsyncPromiseExecutorVisitor::new(context);
+    let promise_like_names =
+      collect_promise_like_names(program, &self.promise_like_names);
+    let resolved_async_call_sites =
+      resolve_scoped_async_executors(program, &promise_like_names);
+    let mut visitor = NoAsyncPromiseExecutorVisitor::new(
+      context,
+      resolved_async_call_sites,
+      promise_like_names,
+    );
     match program {
       ProgramRef::Module(ref m) => m.visit_all_with(&DUMMY_NODE, &mut visitor),
       ProgramRef::Script(ref s) => s.visit_all_with(&DUMMY_NODE, &mut visitor),
@@ -41,6 +119,9 @@ impl LintRule for NoAsyncPromiseExecutor {
   fn docs(&self) -> &'static str {
     r#"Requires that async promise executor functions are not used
 
+This rule is autofixable: when the executor is passed as a literal async
+function or arrow function, the fix removes the `async` keyword.
+
 Promise constructors take an executor function as an argument with `resolve` and 
 `reject` parameters that can be used to control the state of the created Promise.
 This function is allowed to be async but this is generally not a good idea for
@@ -65,17 +146,199 @@ new Promise(async (resolve, reject) => {});
 new Promise(function(resolve, reject) {});
 new Promise((resolve, reject) => {});
 ```
+
+By default, only `Promise` itself is checked, along with any class declared
+in the same module with `extends Promise`. To also check constructors of a
+third-party promise library, the rule supports an `additionalConstructors`
+option:
+
+```json
+{
+  "no-async-promise-executor": {
+    "additionalConstructors": ["Bluebird"]
+  }
+}
+```
+
+Known limitation: wiring this option from the CLI's lint config into the
+constructed rule requires a config-loading step that calls
+`NoAsyncPromiseExecutor::configure` (or constructs the rule with
+`new_with_options`) after `LintRule::new()`. That step is not present in
+this tree, so `additionalConstructors` currently has no effect when set via
+the CLI config; call `new_with_options`/`configure` directly until it's
+wired up.
 "#
   }
 }
 
 struct NoAsyncPromiseExecutorVisitor<'c, 'view> {
   context: &'c mut Context<'view>,
+  /// Call sites of the form `new Promise(exec)` where `exec` resolves,
+  /// through proper lexical scoping, to a `const`/function-declaration
+  /// binding of an async function — keyed by the `NewExpr`'s span start.
+  resolved_async_call_sites: HashSet<BytePos>,
+  /// Constructor identifiers that should be treated as `Promise`-like:
+  /// `Promise` itself, any configured additional names, and any class
+  /// declared in this module with `extends Promise` (or a transitive
+  /// `extends` of one of the above).
+  promise_like_names: HashSet<JsWord>,
 }
 
 impl<'c, 'view> NoAsyncPromiseExecutorVisitor<'c, 'view> {
-  fn new(context: &'c mut Context<'view>) -> Self {
-    Self { context }
+  fn new(
+    context: &'c mut Context<'view>,
+    resolved_async_call_sites: HashSet<BytePos>,
+    promise_like_names: HashSet<JsWord>,
+  ) -> Self {
+    Self {
+      context,
+      resolved_async_call_sites,
+      promise_like_names,
+    }
+  }
+}
+
+/// Starting from the configured promise-like names, also include any class
+/// declared in this module that (transitively) `extends` one of them, e.g.
+/// `class MyPromise extends Promise {}`.
+fn collect_promise_like_names(
+  program: ProgramRef,
+  configured: &HashSet<JsWord>,
+) -> HashSet<JsWord> {
+  let mut names = configured.clone();
+  loop {
+    let mut collector = PromiseSubclassCollector {
+      known: names.clone(),
+      found: HashSet::new(),
+    };
+    match program {
+      ProgramRef::Module(m) => m.visit_all_with(&DUMMY_NODE, &mut collector),
+      ProgramRef::Script(s) => s.visit_all_with(&DUMMY_NODE, &mut collector),
+    }
+    let before = names.len();
+    names.extend(collector.found);
+    if names.len() == before {
+      break;
+    }
+  }
+  names
+}
+
+struct PromiseSubclassCollector {
+  known: HashSet<JsWord>,
+  found: HashSet<JsWord>,
+}
+
+impl VisitAll for PromiseSubclassCollector {
+  noop_visit_type!();
+
+  fn visit_class_decl(&mut self, class_decl: &ClassDecl, _parent: &dyn Node) {
+    if let Some(super_class) = &class_decl.class.super_class {
+      if let Expr::Ident(super_ident) = &**super_class {
+        if self.known.contains(&super_ident.sym) {
+          self.found.insert(class_decl.ident.sym.clone());
+        }
+      }
+    }
+  }
+}
+
+/// For every `new <promise_like>(exec)` call site where `exec` is an
+/// identifier, resolves `exec` through proper lexical scoping — walking
+/// outward from the call site through each enclosing block, matching JS's
+/// own `const` scoping rules — and returns the span-start (`BytePos`) of
+/// every such call site where it resolves to a `const`/function-declaration
+/// binding of an async function.
+///
+/// This is intentionally conservative — `let`/`var` bindings are ignored
+/// since they may be reassigned to something else before being passed to
+/// `new Promise`. Resolving scope-by-scope (rather than collecting every
+/// matching identifier into one flat, file-wide set) is what keeps two
+/// unrelated functions that both happen to declare a local `exec` —
+/// one async, one not — from being confused with each other.
+fn resolve_scoped_async_executors(
+  program: ProgramRef,
+  promise_like_names: &HashSet<JsWord>,
+) -> HashSet<BytePos> {
+  let mut resolver = ScopedExecutorResolver {
+    scopes: vec![HashMap::new()],
+    promise_like_names: promise_like_names.clone(),
+    resolved_async_call_sites: HashSet::new(),
+  };
+  match program {
+    ProgramRef::Module(m) => m.visit_with(&DUMMY_NODE, &mut resolver),
+    ProgramRef::Script(s) => s.visit_with(&DUMMY_NODE, &mut resolver),
+  }
+  resolver.resolved_async_call_sites
+}
+
+/// A single lexical scope's `const`/function-declaration bindings,
+/// recording whether each one is bound to an async function.
+type Scope = HashMap<JsWord, bool>;
+
+struct ScopedExecutorResolver {
+  /// Innermost scope last; a new entry is pushed for every `BlockStmt`
+  /// (matching `const`'s actual block scoping) and popped once its
+  /// children have all been visited.
+  scopes: Vec<Scope>,
+  promise_like_names: HashSet<JsWord>,
+  resolved_async_call_sites: HashSet<BytePos>,
+}
+
+impl ScopedExecutorResolver {
+  fn declare(&mut self, name: JsWord, is_async: bool) {
+    if let Some(scope) = self.scopes.last_mut() {
+      scope.insert(name, is_async);
+    }
+  }
+
+  fn resolve(&self, name: &JsWord) -> Option<bool> {
+    self.scopes.iter().rev().find_map(|scope| scope.get(name).copied())
+  }
+}
+
+impl Visit for ScopedExecutorResolver {
+  noop_visit_type!();
+
+  fn visit_block_stmt(&mut self, block: &BlockStmt, _parent: &dyn Node) {
+    self.scopes.push(Scope::new());
+    block.visit_children_with(self);
+    self.scopes.pop();
+  }
+
+  fn visit_var_decl(&mut self, var_decl: &VarDecl, _parent: &dyn Node) {
+    for decl in &var_decl.decls {
+      if let Pat::Ident(binding_ident) = &decl.name {
+        // `let`/`var` bindings are never treated as async, but they still
+        // shadow any outer binding of the same name.
+        let is_async = var_decl.kind == VarDeclKind::Const
+          && decl.init.as_ref().map_or(false, |init| is_async_function(init));
+        self.declare(binding_ident.id.sym.clone(), is_async);
+      }
+    }
+    var_decl.visit_children_with(self);
+  }
+
+  fn visit_fn_decl(&mut self, fn_decl: &FnDecl, _parent: &dyn Node) {
+    self.declare(fn_decl.ident.sym.clone(), fn_decl.function.is_async);
+    fn_decl.visit_children_with(self);
+  }
+
+  fn visit_new_expr(&mut self, new_expr: &NewExpr, _parent: &dyn Node) {
+    if let Expr::Ident(callee_ident) = &*new_expr.callee {
+      if self.promise_like_names.contains(&callee_ident.sym) {
+        if let Some(args) = &new_expr.args {
+          if let Some(Expr::Ident(executor_ident)) =
+            args.get(0).map(|arg| &*arg.expr)
+          {
+            if self.resolve(&executor_ident.sym) == Some(true) {
+              self.resolved_async_call_sites.insert(new_expr.span.lo());
+            }
+          }
+        }
+      }
+    }
+    new_expr.visit_children_with(self);
   }
 }
 
@@ -88,25 +351,104 @@ fn is_async_function(expr: &Expr) -> bool {
   }
 }
 
+/// If `expr` is an async `FnExpr`/`ArrowExpr` (optionally wrapped in
+/// parens), returns its span, i.e. the span whose first token is the
+/// `async` keyword itself.
+fn async_function_span(expr: &Expr) -> Option<Span> {
+  match expr {
+    Expr::Fn(fn_expr) if fn_expr.function.is_async => Some(fn_expr.function.span),
+    Expr::Arrow(arrow_expr) if arrow_expr.is_async => Some(arrow_expr.span),
+    Expr::Paren(ParenExpr { ref expr, .. }) => async_function_span(&**expr),
+    _ => None,
+  }
+}
+
+const ASYNC_KEYWORD: &str = "async";
+
+/// Builds a fix that deletes the `async` keyword, plus whatever whitespace
+/// (if any) actually follows it in `file_text`, from the start of an async
+/// executor's span. Arrow functions don't require a space after `async`
+/// (`async(resolve, reject) => {}` is valid), so the amount of whitespace
+/// to remove can't be assumed — it's read from the source text instead.
+///
+/// If `fn_span` lies outside `file_text` or doesn't start with the literal
+/// text `"async"` —
+/// which would mean the span computation was wrong somewhere upstream —
+/// no edit is produced rather than guessing at what to delete. A bare
+/// `debug_assert!` isn't enough here: it's compiled out in release builds,
+/// so a wrong span would otherwise silently corrupt the fixed output in
+/// production instead of being caught.
+fn remove_async_keyword_fix(file_text: &str, fn_span: Span) -> LintFix {
+  let start = (fn_span.lo().0 - 1) as usize;
+  let end = (fn_span.hi().0 - 1) as usize;
+  let after_keyword = match file_text
+    .get(start..end)
+    .and_then(|snippet| snippet.strip_prefix(ASYNC_KEYWORD))
+  {
+    Some(rest) => rest,
+    None => {
+      return LintFix {
+        description: "Remove `async` keyword".to_string(),
+        edits: vec![],
+      }
+    }
+  };
+  let whitespace_len = after_keyword
+    .find(|c: char| !c.is_whitespace())
+    .unwrap_or_else(|| after_keyword.len());
+  let delete_len = (ASYNC_KEYWORD.len() + whitespace_len) as u32;
+  let edit_span = Span::new(
+    fn_span.lo(),
+    fn_span.lo() + BytePos(delete_len),
+    fn_span.ctxt(),
+  );
+  LintFix {
+    description: "Remove `async` keyword".to_string(),
+    edits: vec![LintFixEdit::new(edit_span, "")],
+  }
+}
+
 impl<'c, 'view> VisitAll for NoAsyncPromiseExecutorVisitor<'c, 'view> {
   noop_visit_type!();
 
   fn visit_new_expr(&mut self, new_expr: &NewExpr, _parent: &dyn Node) {
     if let Expr::Ident(ident) = &*new_expr.callee {
-      let name = ident.sym.as_ref();
-      if name != "Promise" {
+      if !self.promise_like_names.contains(&ident.sym) {
         return;
       }
 
       if let Some(args) = &new_expr.args {
         if let Some(first_arg) = args.get(0) {
-          if is_async_function(&*first_arg.expr) {
-            self.context.add_diagnostic_with_hint(
-              new_expr.span,
-              CODE,
-              MESSAGE,
-              HINT,
-            );
+          let literal_async_span = async_function_span(&*first_arg.expr);
+          let is_async_executor = literal_async_span.is_some()
+            || self
+              .resolved_async_call_sites
+              .contains(&new_expr.span.lo());
+          if is_async_executor {
+            match literal_async_span {
+              Some(fn_span) => {
+                let fix = remove_async_keyword_fix(
+                  self.context.file_text(),
+                  fn_span,
+                );
+                self.context.add_diagnostic_with_fix(
+                  new_expr.span,
+                  CODE,
+                  MESSAGE,
+                  HINT,
+                  fix,
+                )
+              }
+              // The executor was passed by reference (e.g. `new
+              // Promise(exec)`); there's no `async` keyword at this call
+              // site to remove, so fall back to a plain hint.
+              None => self.context.add_diagnostic_with_hint(
+                new_expr.span,
+                CODE,
+                MESSAGE,
+                HINT,
+              ),
+            }
           }
         }
       }
@@ -127,6 +469,31 @@ mod tests {
       "new Promise((resolve, reject) => {}, async function unrelated() {})",
       "new Foo(async (resolve, reject) => {})",
       "new class { foo() { new Promise(function(resolve, reject) {}); } }",
+      // `let`/`var` bindings are not tracked, since they may be reassigned
+      "let exec = async (resolve, reject) => {}; new Promise(exec);",
+      "var exec = async (resolve, reject) => {}; new Promise(exec);",
+      "const exec = function(resolve, reject) {}; new Promise(exec);",
+      // unrelated classes are not treated as Promise-like
+      "class MyThing {}; new MyThing(async () => {});",
+      // two unrelated functions each have their own, local `exec` binding;
+      // the async one in `b` must not leak into `a`'s unrelated scope
+      r#"
+function a() {
+  const exec = function(resolve, reject) {};
+  new Promise(exec);
+}
+function b() {
+  const exec = async (resolve, reject) => {};
+}
+      "#,
+      // shadowing: the inner, non-async `exec` wins over the outer async one
+      r#"
+const exec = async (resolve, reject) => {};
+function a() {
+  const exec = function(resolve, reject) {};
+  new Promise(exec);
+}
+      "#,
     };
   }
 
@@ -138,6 +505,20 @@ mod tests {
       "new Promise(async function foo(resolve, reject) {});": [{ col: 0, message: MESSAGE, hint: HINT }],
       "new Promise(async (resolve, reject) => {});": [{ col: 0, message: MESSAGE, hint: HINT }],
       "new Promise(((((async () => {})))));": [{ col: 0, message: MESSAGE, hint: HINT }],
+      // executor passed indirectly via a `const` binding
+      "const exec = async (resolve, reject) => {}; new Promise(exec);": [{ col: 44, message: MESSAGE, hint: HINT }],
+      "async function exec(resolve, reject) {}; new Promise(exec);": [{ col: 41, message: MESSAGE, hint: HINT }],
+      // resolution still finds the binding when both the declaration and
+      // the call site are nested inside the same function scope
+      r#"
+function b() {
+  const exec = async (resolve, reject) => {};
+  new Promise(exec);
+}
+      "#: [{ line: 4, col: 2, message: MESSAGE, hint: HINT }],
+      // a class declared with `extends Promise` is automatically treated
+      // as Promise-like
+      "class MyPromise extends Promise {}; new MyPromise(async () => {});": [{ col: 36, message: MESSAGE, hint: HINT }],
       // nested
       r#"
 const a = new class {
@@ -148,4 +529,93 @@ const a = new class {
       "#: [{ line: 4, col: 12, message: MESSAGE, hint: HINT }],
     }
   }
+
+  #[test]
+  fn no_async_promise_executor_options_default_to_promise_only() {
+    let rule = NoAsyncPromiseExecutor::default();
+    assert!(rule.promise_like_names.contains(&JsWord::from("Promise")));
+    assert_eq!(rule.promise_like_names.len(), 1);
+  }
+
+  #[test]
+  fn no_async_promise_executor_options_additional_constructors() {
+    let rule =
+      NoAsyncPromiseExecutor::new_with_options(NoAsyncPromiseExecutorOptions {
+        additional_constructors: vec!["Bluebird".to_string()],
+      });
+    assert!(rule.promise_like_names.contains(&JsWord::from("Promise")));
+    assert!(rule.promise_like_names.contains(&JsWord::from("Bluebird")));
+  }
+
+  #[test]
+  fn no_async_promise_executor_configure_replaces_additional_constructors() {
+    let mut rule =
+      NoAsyncPromiseExecutor::new_with_options(NoAsyncPromiseExecutorOptions {
+        additional_constructors: vec!["Q".to_string()],
+      });
+    rule.configure(NoAsyncPromiseExecutorOptions {
+      additional_constructors: vec!["Bluebird".to_string()],
+    });
+    assert!(rule.promise_like_names.contains(&JsWord::from("Promise")));
+    assert!(rule.promise_like_names.contains(&JsWord::from("Bluebird")));
+    assert!(!rule.promise_like_names.contains(&JsWord::from("Q")));
+  }
+
+  fn fn_span_of(source: &str, fn_snippet: &str) -> Span {
+    let lo = source.find(fn_snippet).unwrap() as u32 + 1; // BytePos is 1-indexed
+    Span::new(
+      BytePos(lo),
+      BytePos(lo + fn_snippet.len() as u32),
+      Default::default(),
+    )
+  }
+
+  #[test]
+  fn remove_async_keyword_fix_deletes_async_and_space() {
+    use crate::fixer::apply_fixes;
+
+    let source = "new Promise(async (resolve, reject) => {});";
+    let fn_span = fn_span_of(source, "async (resolve, reject) => {}");
+    let fix = remove_async_keyword_fix(source, fn_span);
+    let fixed = apply_fixes(source, BytePos(1), &[fix]);
+    assert_eq!(fixed, "new Promise((resolve, reject) => {});");
+  }
+
+  #[test]
+  fn remove_async_keyword_fix_handles_no_space_before_params() {
+    use crate::fixer::apply_fixes;
+
+    // `async(...)` with no space is valid arrow function syntax; the fix
+    // must not eat into the parameter list's opening paren.
+    let source = "new Promise(async(resolve, reject) => {});";
+    let fn_span = fn_span_of(source, "async(resolve, reject) => {}");
+    let fix = remove_async_keyword_fix(source, fn_span);
+    let fixed = apply_fixes(source, BytePos(1), &[fix]);
+    assert_eq!(fixed, "new Promise((resolve, reject) => {});");
+  }
+
+  #[test]
+  fn remove_async_keyword_fix_handles_async_function_expressions() {
+    use crate::fixer::apply_fixes;
+
+    // Exercises the `Expr::Fn` branch of `async_function_span`, not just
+    // the `Expr::Arrow` one covered above.
+    let source = "new Promise(async function(resolve, reject) {});";
+    let fn_span = fn_span_of(source, "async function(resolve, reject) {}");
+    let fix = remove_async_keyword_fix(source, fn_span);
+    let fixed = apply_fixes(source, BytePos(1), &[fix]);
+    assert_eq!(fixed, "new Promise(function(resolve, reject) {});");
+  }
+
+  #[test]
+  fn remove_async_keyword_fix_returns_no_op_when_span_does_not_start_with_async(
+  ) {
+    // A bare debug_assert! would be compiled out in release; if the span
+    // computation is ever wrong upstream, this must not silently delete
+    // the wrong bytes.
+    let source = "new Promise(function(resolve, reject) {});";
+    let fn_span = fn_span_of(source, "function(resolve, reject) {}");
+    let fix = remove_async_keyword_fix(source, fn_span);
+    assert!(fix.edits.is_empty());
+  }
 }
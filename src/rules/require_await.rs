@@ -0,0 +1,277 @@
+// Copyright 2020-2021 the Deno authors. All rights reserved. MIT license.
+use super::{Context, LintRule, ProgramRef, DUMMY_NODE};
+use std::collections::HashSet;
+use swc_common::{BytePos, Span, Spanned};
+use swc_ecmascript::ast::{
+  ArrowExpr, BlockStmtOrExpr, ClassMethod, Function, MethodProp, PrivateMethod,
+};
+use swc_ecmascript::visit::noop_visit_type;
+use swc_ecmascript::visit::Node;
+use swc_ecmascript::visit::{Visit, VisitAll, VisitAllWith, VisitWith};
+
+pub struct RequireAwait;
+
+const CODE: &str = "require-await";
+const MESSAGE: &str = "Async function has no `await` expression";
+const HINT: &str =
+  "Remove `async` keyword, or use `await` inside the function body";
+
+impl LintRule for RequireAwait {
+  fn new() -> Box<Self> {
+    Box::new(RequireAwait)
+  }
+
+  fn tags(&self) -> &'static [&'static str] {
+    &["recommended"]
+  }
+
+  fn code(&self) -> &'static str {
+    CODE
+  }
+
+  fn lint_program<'view>(
+    &self,
+    context: &mut Context<'view>,
+    program: ProgramRef<'view>,
+  ) {
+    let mut visitor = RequireAwaitVisitor::new(context);
+    match program {
+      ProgramRef::Module(ref m) => m.visit_all_with(&DUMMY_NODE, &mut visitor),
+      ProgramRef::Script(ref s) => s.visit_all_with(&DUMMY_NODE, &mut visitor),
+    }
+  }
+
+  fn docs(&self) -> &'static str {
+    r#"Disallows async functions that have no `await` expression
+
+Async functions that never use `await` add the overhead of promise handling
+without needing it, and are often a sign that the `async` keyword was added
+by mistake or is left over from a refactor. If a function genuinely performs
+no asynchronous work, it should not be declared `async`.
+
+### Invalid:
+```typescript
+async function f() {
+  doSomethingSync();
+}
+
+const g = async () => {
+  doSomethingSync();
+};
+```
+
+### Valid:
+```typescript
+async function f() {
+  await doSomethingAsync();
+}
+
+const g = async () => await doSomethingAsync();
+
+// functions with no body (e.g. overloads, abstract methods) are exempt
+declare function h(): Promise<void>;
+```
+"#
+  }
+}
+
+struct RequireAwaitVisitor<'c, 'view> {
+  context: &'c mut Context<'view>,
+  /// Span-starts of `Function`s already reported against a more specific
+  /// enclosing span (see `visit_class_method`/`visit_private_method`/
+  /// `visit_method_prop` below), so the generic `visit_function` fallback
+  /// doesn't report them a second time against the bare `Function` span.
+  reported_function_spans: HashSet<BytePos>,
+}
+
+impl<'c, 'view> RequireAwaitVisitor<'c, 'view> {
+  fn new(context: &'c mut Context<'view>) -> Self {
+    Self {
+      context,
+      reported_function_spans: HashSet::new(),
+    }
+  }
+
+  fn check_function(&mut self, function: &Function, report_span: Span) {
+    if !function.is_async {
+      return;
+    }
+
+    // Functions without a body (overloads, ambient declarations, abstract
+    // methods) have nothing to walk, so they are exempt from this rule.
+    let body = match &function.body {
+      Some(body) => body,
+      None => return,
+    };
+
+    let mut finder = AwaitFinder::default();
+    body.visit_with(&DUMMY_NODE, &mut finder);
+    if !finder.found {
+      self
+        .context
+        .add_diagnostic_with_hint(report_span, CODE, MESSAGE, HINT);
+    }
+  }
+
+  /// For a class/object method, `function.span` only covers the parameter
+  /// list onward: the `async` keyword and method name live on the enclosing
+  /// `ClassMethod`/`PrivateMethod`/`MethodProp` node instead. Reporting
+  /// against the bare `function.span` would point the caret at `(`, which
+  /// isn't useful, so the caller passes a span starting at the method's key
+  /// instead.
+  fn check_method(&mut self, function: &Function, key_span: Span) {
+    self.reported_function_spans.insert(function.span.lo());
+    let report_span =
+      Span::new(key_span.lo(), function.span.hi(), function.span.ctxt());
+    self.check_function(function, report_span);
+  }
+
+  fn check_arrow(&mut self, arrow_expr: &ArrowExpr) {
+    if !arrow_expr.is_async {
+      return;
+    }
+
+    let mut finder = AwaitFinder::default();
+    match &arrow_expr.body {
+      BlockStmtOrExpr::BlockStmt(block) => {
+        block.visit_with(&DUMMY_NODE, &mut finder)
+      }
+      BlockStmtOrExpr::Expr(expr) => {
+        expr.visit_with(&DUMMY_NODE, &mut finder)
+      }
+    }
+    if !finder.found {
+      self
+        .context
+        .add_diagnostic_with_hint(arrow_expr.span, CODE, MESSAGE, HINT);
+    }
+  }
+}
+
+impl<'c, 'view> VisitAll for RequireAwaitVisitor<'c, 'view> {
+  noop_visit_type!();
+
+  fn visit_function(&mut self, function: &Function, _parent: &dyn Node) {
+    if self.reported_function_spans.contains(&function.span.lo()) {
+      return;
+    }
+    self.check_function(function, function.span);
+  }
+
+  fn visit_arrow_expr(&mut self, arrow_expr: &ArrowExpr, _parent: &dyn Node) {
+    self.check_arrow(arrow_expr);
+  }
+
+  fn visit_class_method(
+    &mut self,
+    class_method: &ClassMethod,
+    _parent: &dyn Node,
+  ) {
+    self.check_method(&class_method.function, class_method.key.span());
+  }
+
+  fn visit_private_method(
+    &mut self,
+    private_method: &PrivateMethod,
+    _parent: &dyn Node,
+  ) {
+    self
+      .check_method(&private_method.function, private_method.key.span());
+  }
+
+  fn visit_method_prop(&mut self, method_prop: &MethodProp, _parent: &dyn Node) {
+    self.check_method(&method_prop.function, method_prop.key.span());
+  }
+}
+
+/// Looks for an `await` expression (including `for await (... of ...)`)
+/// within a function's own body, without descending into the bodies of any
+/// nested functions, arrow functions, or methods it contains, since those
+/// introduce their own, separate async scope.
+#[derive(Default)]
+struct AwaitFinder {
+  found: bool,
+}
+
+impl Visit for AwaitFinder {
+  fn visit_await_expr(
+    &mut self,
+    _await_expr: &swc_ecmascript::ast::AwaitExpr,
+    _parent: &dyn Node,
+  ) {
+    self.found = true;
+  }
+
+  fn visit_for_of_stmt(
+    &mut self,
+    for_of_stmt: &swc_ecmascript::ast::ForOfStmt,
+    parent: &dyn Node,
+  ) {
+    if for_of_stmt.await_token.is_some() {
+      self.found = true;
+    }
+    swc_ecmascript::visit::visit_for_of_stmt(self, for_of_stmt, parent);
+  }
+
+  fn visit_function(&mut self, _function: &Function, _parent: &dyn Node) {
+    // Do not descend into nested functions/methods; they have their own
+    // async scope.
+  }
+
+  fn visit_arrow_expr(&mut self, _arrow_expr: &ArrowExpr, _parent: &dyn Node) {
+    // Do not descend into nested arrow functions; they have their own
+    // async scope.
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn require_await_valid() {
+    assert_lint_ok! {
+      RequireAwait,
+      "async function f() { await doSomething(); }",
+      "const f = async () => await doSomething();",
+      "const f = async () => { await doSomething(); };",
+      "async function f() { for await (const x of xs) {} }",
+      "class A { async f() { await doSomething(); } }",
+      "const o = { async f() { await doSomething(); } };",
+      "class A { async #f() { await doSomething(); } }",
+      "function f() { doSomethingSync(); }",
+      "async function f() {}",
+      "async function* f() { await doSomething(); }",
+      // nested async functions are independent scopes
+      "async function f() { await doSomething(); function g() {} }",
+      "async function outer() { await Promise.resolve(); async function inner() { return 1; } }",
+      // no body: ambient/overload declarations are exempt
+      "declare function f(): Promise<void>;",
+    };
+  }
+
+  #[test]
+  fn require_await_invalid() {
+    assert_lint_err! {
+      RequireAwait,
+      "async function f() { doSomethingSync(); }": [{ col: 0, message: MESSAGE, hint: HINT }],
+      // an empty async function has no `await` either
+      "async function f() {}": [{ col: 0, message: MESSAGE, hint: HINT }],
+      "const f = async () => doSomethingSync();": [{ col: 10, message: MESSAGE, hint: HINT }],
+      "const f = async () => { doSomethingSync(); };": [{ col: 10, message: MESSAGE, hint: HINT }],
+      // For a class/object method, the diagnostic is reported starting at
+      // the method's key (`function.span` alone would point at `(`, which
+      // isn't useful), since the `async` keyword and method name live on
+      // the enclosing `ClassMethod`/`MethodProp` node rather than on
+      // `function.span`.
+      "class A { async f() { doSomethingSync(); } }": [{ col: 16, message: MESSAGE, hint: HINT }],
+      "const o = { async f() { doSomethingSync(); } };": [{ col: 18, message: MESSAGE, hint: HINT }],
+      // private class methods are reported the same way
+      "class A { async #f() { doSomethingSync(); } }": [{ col: 16, message: MESSAGE, hint: HINT }],
+      // async generator that yields but never awaits is still flagged
+      "async function* f() { yield 1; }": [{ col: 0, message: MESSAGE, hint: HINT }],
+      // inner function is flagged even though the outer one awaits
+      "async function outer() { await Promise.resolve(); async function inner() { doSomethingSync(); } }": [{ col: 50, message: MESSAGE, hint: HINT }],
+    }
+  }
+}